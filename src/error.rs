@@ -0,0 +1,59 @@
+use std::fmt;
+use std::io;
+use std::process::ExitStatus;
+
+/// Errors that can occur while writing an `LpProblem` to disk or solving it
+/// through one of the `solvers` backends.
+///
+/// Replaces the ad-hoc `Result<_, String>` and panicking `.expect(...)`
+/// calls the solver pipeline used to rely on, so callers can match on a
+/// specific failure instead of losing the process to an abort.
+#[derive(Debug)]
+pub enum SolverError {
+    /// The solver process could not be started, e.g. the executable is
+    /// missing from `PATH`.
+    Spawn(String),
+    /// Writing the `.lp` model file to disk failed.
+    ModelWrite(io::Error),
+    /// The solver's stdout was not valid UTF-8.
+    NonUtf8Output,
+    /// The solver process exited with a non-zero status.
+    SolverFailed(ExitStatus),
+    /// The `.sol` solution file could not be parsed.
+    SolutionParse { line: usize, reason: String },
+    /// The model cannot be represented in the form the solver expects, e.g.
+    /// a nonlinear term was passed to a solver that only handles linear
+    /// expressions.
+    InvalidModel(String),
+    /// An I/O failure unrelated to writing the model, e.g. reading the
+    /// `.sol` file or polling the child process.
+    Io(io::Error),
+}
+
+impl fmt::Display for SolverError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &SolverError::Spawn(ref name) => write!(f, "could not start the {} solver", name),
+            &SolverError::ModelWrite(ref e) => write!(f, "failed to write the LP model: {}", e),
+            &SolverError::NonUtf8Output => write!(f, "the solver produced non-UTF-8 output"),
+            &SolverError::SolverFailed(ref status) => {
+                write!(f, "the solver exited with {}", status)
+            }
+            &SolverError::SolutionParse { line, ref reason } => write!(
+                f,
+                "could not parse the solution file at line {}: {}",
+                line, reason
+            ),
+            &SolverError::InvalidModel(ref reason) => write!(f, "invalid model: {}", reason),
+            &SolverError::Io(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SolverError {}
+
+impl From<io::Error> for SolverError {
+    fn from(e: io::Error) -> SolverError {
+        SolverError::Io(e)
+    }
+}
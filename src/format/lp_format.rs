@@ -1,13 +1,29 @@
 use std::fs::File;
-use std::io::prelude::*;
-use std::io::Result;
+use std::io::{self, BufWriter, Write};
 
 use dsl::*;
 use dsl::Constraint::*;
 use dsl::LpExpression::*;
+use error::SolverError;
+
+/// Default physical line-length limit historically enforced by the LP
+/// format readers in CPLEX/Gurobi; a wider constraint risks being rejected
+/// by those solvers, so `format` starts a continuation line at the next term
+/// boundary once a line has grown past it. The line that first crosses the
+/// limit is not shortened, so it can end a term or two beyond it. Callers that need a different limit can go through
+/// `format_with_max_width`/`write_lp_with_max_width` instead.
+const LP_FILE_MAX_LINE_WIDTH: usize = 255;
 
 pub trait LpFileFormat {
-    fn format<W: std::fmt::Write>(&self, w: &mut W) -> std::fmt::Result;
+    fn format_with_max_width<W: std::fmt::Write>(
+        &self,
+        w: &mut W,
+        max_line_width: usize,
+    ) -> std::fmt::Result;
+
+    fn format<W: std::fmt::Write>(&self, w: &mut W) -> std::fmt::Result {
+        self.format_with_max_width(w, LP_FILE_MAX_LINE_WIDTH)
+    }
 
     fn to_lp_file_format(&self) -> String {
         let mut buffer = String::new();
@@ -15,22 +31,114 @@ pub trait LpFileFormat {
         buffer
     }
 
-    fn write_lp(&self, file_model: &str) -> Result<()> {
-        let mut buffer = File::create(file_model)?;
-        buffer.write(self.to_lp_file_format().as_bytes())?;
+    fn write_lp(&self, file_model: &str) -> Result<(), SolverError> {
+        self.write_lp_with_max_width(file_model, LP_FILE_MAX_LINE_WIDTH)
+    }
+
+    fn write_lp_with_max_width(
+        &self,
+        file_model: &str,
+        max_line_width: usize,
+    ) -> Result<(), SolverError> {
+        let file = File::create(file_model).map_err(SolverError::ModelWrite)?;
+        let mut writer = IoFmtWriter::new(BufWriter::new(file));
+
+        let format_result = self.format_with_max_width(&mut writer, max_line_width);
+        if let Some(io_err) = writer.error.take() {
+            // `format_result` is just a generic `fmt::Error` here - the real
+            // cause is the write failure we stashed in `write_str`.
+            return Err(SolverError::ModelWrite(io_err));
+        }
+        format_result.map_err(|_| {
+            SolverError::ModelWrite(io::Error::new(
+                io::ErrorKind::Other,
+                "failed to format the LP model",
+            ))
+        })?;
+
+        // `BufWriter` only flushes on drop, and that implicit flush ignores
+        // errors - flush explicitly so a write failure on the last buffered
+        // bytes is reported instead of silently producing a truncated file.
+        writer.inner.flush().map_err(SolverError::ModelWrite)?;
+        Ok(())
+    }
+}
+
+/// Adapts a `std::io::Write` (here, a `BufWriter<File>`) so `format` can
+/// stream straight into it instead of materializing the whole model as one
+/// `String` first. Since `fmt::Write::write_str` can't carry an `io::Error`,
+/// the real error is stashed in `error` and recovered by the caller.
+struct IoFmtWriter<W: Write> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> IoFmtWriter<W> {
+    fn new(inner: W) -> Self {
+        IoFmtWriter { inner, error: None }
+    }
+}
+
+impl<W: Write> std::fmt::Write for IoFmtWriter<W> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            std::fmt::Error
+        })
+    }
+}
+
+/// Tracks the current output column so `format` can start a continuation
+/// line between terms once it has grown past the configured `max_width`,
+/// without ever breaking in the middle of a token. The column is counted
+/// from where the wrapped expression starts; a prefix written before it
+/// (such as `  c1: `) is not counted.
+struct LineWrappingWriter<'a, W: std::fmt::Write + 'a> {
+    inner: &'a mut W,
+    column: usize,
+    max_width: usize,
+}
+
+impl<'a, W: std::fmt::Write> LineWrappingWriter<'a, W> {
+    fn new(inner: &'a mut W, max_width: usize) -> Self {
+        LineWrappingWriter {
+            inner,
+            column: 0,
+            max_width,
+        }
+    }
+
+    /// Call between terms (never inside one) to start a continuation line
+    /// once the current line has grown past the configured width.
+    fn wrap_if_needed(&mut self) -> std::fmt::Result {
+        if self.column > self.max_width {
+            self.inner.write_str("\n    ")?;
+            self.column = 4;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, W: std::fmt::Write> std::fmt::Write for LineWrappingWriter<'a, W> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.inner.write_str(s)?;
+        match s.rfind('\n') {
+            Some(pos) => self.column = s.len() - pos - 1,
+            None => self.column += s.len(),
+        }
         Ok(())
     }
 }
 
 impl LpFileFormat for LpProblem {
 
-    fn format<W: std::fmt::Write>(&self, w: &mut W) -> std::fmt::Result {
+    fn format_with_max_width<W: std::fmt::Write>(&self, w: &mut W, max_line_width: usize) -> std::fmt::Result {
         write!(w, "\\ {}\n\n", &self.name)?;
 
-        format_objective_lp_file_block(self, w)?;
+        format_objective_lp_file_block(self, w, max_line_width)?;
 
         write!(w, "\n\nSubject To\n")?; // TODO: check emptyness
-        format_constraints_lp_file_block(self, w)?;
+        format_constraints_lp_file_block(self, w, max_line_width)?;
 
         writeln!(w, "\nBounds")?; // TODO: check emptyness
         format_bounds_lp_file_block(self, w)?;
@@ -46,7 +154,7 @@ impl LpFileFormat for LpProblem {
 }
 
 fn format_objective_lp_file_block<W: std::fmt::Write>(
-        prob: &LpProblem, w: &mut W) -> std::fmt::Result {
+        prob: &LpProblem, w: &mut W, max_line_width: usize) -> std::fmt::Result {
     // Write objectives
     let obj_type = match prob.objective_type {
         LpObjective::Maximize => "Maximize\n  ",
@@ -54,20 +162,20 @@ fn format_objective_lp_file_block<W: std::fmt::Write>(
     };
     match prob.obj_expr {
         Some(ref expr) => {
-            write!(w, "{}obj: ", obj_type)?; 
-            expr.format(w)
+            write!(w, "{}obj: ", obj_type)?;
+            expr.format_with_max_width(w, max_line_width)
         },
         _ => Ok(()),
     }
 }
 
 fn format_constraints_lp_file_block<W: std::fmt::Write>(
-        prob: &LpProblem, w: &mut W) -> std::fmt::Result {
+        prob: &LpProblem, w: &mut W, max_line_width: usize) -> std::fmt::Result {
     let mut constraints = prob.constraints.iter();
     let mut index = 1;
     while let Some(ref constraint) = constraints.next() {
         write!(w, "  c{}: ", index.to_string())?;
-        constraint.format(w)?;
+        constraint.format_with_max_width(w, max_line_width)?;
         writeln!(w)?;
         index += 1;
     }
@@ -138,26 +246,28 @@ fn format_binaries_lp_file_block<W: std::fmt::Write>(
 }
 
 impl LpFileFormat for LpExpression {
-    fn format<W: std::fmt::Write>(&self, w: &mut W) -> std::fmt::Result {
-        format(&self, w, false)
+    fn format_with_max_width<W: std::fmt::Write>(&self, w: &mut W, max_line_width: usize) -> std::fmt::Result {
+        let mut wrapped = LineWrappingWriter::new(w, max_line_width);
+        format(&self, &mut wrapped, false)
     }
 
 }
 
 fn format<W: std::fmt::Write>(
-        e: &LpExpression, w: &mut W, with_parenthesis: bool
+        e: &LpExpression, w: &mut LineWrappingWriter<W>, with_parenthesis: bool
         ) -> std::fmt::Result {
     let str_left_mult = if with_parenthesis { "(" } else { "" };
     let str_right_mult = if with_parenthesis { ")" } else { "" };
     let str_op_mult = if with_parenthesis { " * " } else { " " };
     match e {
-        &LitVal(n) => { 
+        &LitVal(n) => {
             write!(w, "{}", n.to_string())
         },
         &AddExpr(ref e1, ref e2) => {
             write!(w, "{}", str_left_mult.to_string())?;
             format(e1, w, with_parenthesis)?;
             write!(w, " + ")?;
+            w.wrap_if_needed()?;
             format(e2, w, with_parenthesis)?;
             write!(w, "{}", str_right_mult.to_string())
         }
@@ -165,6 +275,7 @@ fn format<W: std::fmt::Write>(
             write!(w, "{}", str_left_mult.to_string())?;
             format(e1, w, with_parenthesis)?;
             write!(w, " - ")?;
+            w.wrap_if_needed()?;
             format(e2, w, with_parenthesis)?;
             write!(w, "{}", str_right_mult.to_string())
         }
@@ -209,13 +320,74 @@ fn format<W: std::fmt::Write>(
 }
 
 impl LpFileFormat for LpConstraint {
-    fn format<W: std::fmt::Write>(&self, w: &mut W) -> std::fmt::Result {
-        self.0.format(w)?;
+    fn format_with_max_width<W: std::fmt::Write>(&self, w: &mut W, max_line_width: usize) -> std::fmt::Result {
+        let mut wrapped = LineWrappingWriter::new(w, max_line_width);
+        format(&self.0, &mut wrapped, false)?;
         match self.1 {
-            GreaterOrEqual => write!(w, " >= ")?,
-            LessOrEqual => write!(w, " <= ")?,
-            Equal => write!(w, " = ")?,
+            GreaterOrEqual => write!(wrapped, " >= ")?,
+            LessOrEqual => write!(wrapped, " <= ")?,
+            Equal => write!(wrapped, " = ")?,
         };
-        self.2.format(w)
+        wrapped.wrap_if_needed()?;
+        format(&self.2, &mut wrapped, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var(name: &str) -> LpExpression {
+        ConsCont(LpContinuous {
+            name: name.to_string(),
+            lower_bound: None,
+            upper_bound: None,
+        })
+    }
+
+    // alpha + beta + gamma + delta
+    fn long_sum() -> LpExpression {
+        AddExpr(
+            Box::new(AddExpr(
+                Box::new(AddExpr(Box::new(var("alpha")), Box::new(var("beta")))),
+                Box::new(var("gamma")),
+            )),
+            Box::new(var("delta")),
+        )
+    }
+
+    #[test]
+    fn wraps_onto_continuation_line_once_past_max_width() {
+        let mut out = String::new();
+        long_sum().format_with_max_width(&mut out, 10).unwrap();
+
+        assert!(
+            out.contains("\n    "),
+            "expected a continuation line, got: {:?}",
+            out
+        );
+    }
+
+    #[test]
+    fn does_not_wrap_when_under_max_width() {
+        let mut out = String::new();
+        long_sum().format_with_max_width(&mut out, 255).unwrap();
+
+        assert!(!out.contains('\n'), "expected a single line, got: {:?}", out);
+    }
+
+    #[test]
+    fn never_breaks_in_the_middle_of_a_token() {
+        let mut out = String::new();
+        long_sum().format_with_max_width(&mut out, 10).unwrap();
+
+        for name in &["alpha", "beta", "gamma", "delta"] {
+            assert!(
+                out.contains(name),
+                "expected {} to appear intact, got: {:?}",
+                name,
+                out
+            );
+        }
     }
 }
@@ -0,0 +1,71 @@
+use std::fs;
+use std::process::{Child, ExitStatus};
+
+use dsl::LpProblem;
+use error::SolverError;
+use solvers::{Solution, SolverWithSolutionParsing, Status};
+
+/// Lets `SolveHandle::wait` recover the same stdout-based `Status` that
+/// `SolverTrait::run` does, so the sync and async APIs report identical
+/// outcomes for the same run instead of `wait` always reporting optimal.
+pub trait SolverStdoutStatus {
+    fn detect_status(&self, stdout: &[u8]) -> Status;
+}
+
+/// A handle to an in-flight solve started by `AsyncSolverTrait::spawn`.
+///
+/// Wraps the running `Child` together with the paths of the temporary
+/// model/solution files, so the solution can be parsed once the process
+/// exits, reusing `SolverWithSolutionParsing::read_solution`.
+pub struct SolveHandle {
+    child: Child,
+    file_model: String,
+    temp_solution_file: String,
+}
+
+impl SolveHandle {
+    pub fn new(child: Child, file_model: String, temp_solution_file: String) -> SolveHandle {
+        SolveHandle {
+            child,
+            file_model,
+            temp_solution_file,
+        }
+    }
+
+    /// Polls the child process without blocking, returning `Ok(None)` while
+    /// the solver is still running.
+    pub fn try_status(&mut self) -> Result<Option<ExitStatus>, SolverError> {
+        Ok(self.child.try_wait()?)
+    }
+
+    /// Blocks until the solver process exits, then parses the resulting
+    /// solution file with the same logic the synchronous solvers use,
+    /// overlaying the status `SolverTrait::run` would have detected from
+    /// stdout instead of assuming every cleanly-parsed solution is optimal.
+    pub fn wait<'a, S: SolverWithSolutionParsing + SolverStdoutStatus>(
+        self,
+        solver: &S,
+        problem: &'a LpProblem,
+    ) -> Result<Solution<'a>, SolverError> {
+        let output = self.child.wait_with_output()?;
+        let _ = fs::remove_file(&self.file_model);
+
+        if !output.status.success() {
+            return Err(SolverError::SolverFailed(output.status));
+        }
+
+        let status = solver.detect_status(&output.stdout);
+        solver
+            .read_solution(&self.temp_solution_file, Some(problem))
+            .map(|solution| Solution { status, ..solution.clone() })
+    }
+}
+
+/// Mirrors `SolverTrait::run`, but starts the solver process with
+/// `Command::spawn` and returns a `SolveHandle` immediately instead of
+/// blocking until it exits. Lets callers launch several solvers
+/// concurrently and collect `Solution`s as each one finishes.
+pub trait AsyncSolverTrait {
+    type P;
+    fn spawn(&self, problem: &Self::P) -> Result<SolveHandle, SolverError>;
+}
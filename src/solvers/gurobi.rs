@@ -5,11 +5,26 @@ use std::fs;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufReader, BufRead, Write};
-use std::process::{Command, Output};
+use std::process::{Command, Output, Stdio};
 
 use dsl::LpProblem;
+use error::SolverError;
 use format::lp_format::*;
 use solvers::{Status, SolverTrait, SolverWithSolutionParsing, Solution};
+use solvers::async_solver::{AsyncSolverTrait, SolveHandle, SolverStdoutStatus};
+
+/// Reads Gurobi's stdout for the markers it prints alongside the `.sol`
+/// file, since `read_specific_solution` alone can't tell optimal apart from
+/// sub-optimal/infeasible.
+fn status_from_stdout(stdout: &str) -> Status {
+    if stdout.contains("Optimal objective") {
+        Status::Optimal
+    } else if stdout.contains("infesible") {
+        Status::Infeasible
+    } else {
+        Status::SubOptimal
+    }
+}
 
 pub struct GurobiSolver {
     name: String,
@@ -33,36 +48,30 @@ impl GurobiSolver {
         }
     }
 
-    fn process_output<'a>(&self, problem: &'a LpProblem, r: Output) -> Result<Solution<'a>, String> {
-        let mut status = Status::SubOptimal;
-        let result = String::from_utf8(r.stdout).expect("");
-        if result.contains("Optimal objective")
-        {
-            status = Status::Optimal;
-        } else if result.contains("infesible") {
-            status = Status::Infeasible;
-        }
+    fn process_output<'a>(&self, problem: &'a LpProblem, r: Output) -> Result<Solution<'a>, SolverError> {
+        let result = String::from_utf8(r.stdout).map_err(|_| SolverError::NonUtf8Output)?;
+        let status = status_from_stdout(&result);
         if r.status.success() {
             self.read_solution(&self.temp_solution_file, Some(problem)).map(|solution| Solution {status, ..solution.clone()} )
         } else {
-            File::create(&format!("{}.stderr", problem.unique_name)).expect("couldn't open").write_all(&r.stderr).expect("couldn't write error");
-            File::create(&format!("{}.stdout", problem.unique_name)).expect("couldn't open").write_all(&result.as_bytes()).expect("couldn't write stdout");
+            File::create(&format!("{}.stderr", problem.unique_name))?.write_all(&r.stderr)?;
+            File::create(&format!("{}.stdout", problem.unique_name))?.write_all(&result.as_bytes())?;
 
-            Err(r.status.to_string())
+            Err(SolverError::SolverFailed(r.status))
         }
     }
 }
 
 impl SolverWithSolutionParsing for GurobiSolver {
-    fn read_specific_solution<'a>(&self, f: &File, problem: Option<&'a LpProblem>) -> Result<Solution<'a>, String> {
+    fn read_specific_solution<'a>(&self, f: &File, problem: Option<&'a LpProblem>) -> Result<Solution<'a>, SolverError> {
         let mut vars_value: HashMap<_, _> = HashMap::new();
         let mut file = BufReader::new(f);
         let mut buffer = String::new();
         let _ = file.read_line(&mut buffer);
 
         if let Some(_) = buffer.split(" ").next() {
-            for line in file.lines() {
-                let l = line.unwrap();
+            for (index, line) in file.lines().enumerate() {
+                let l = line?;
 
                 // Gurobi version 7 add comments on the header file
                 if let Some('#') = l.chars().next() {
@@ -75,14 +84,14 @@ impl SolverWithSolutionParsing for GurobiSolver {
                         Ok(n) => {
                             vars_value.insert(result_line[0].to_string(), n);
                         }
-                        Err(e) => return Err(format!("{}", e.to_string())),
+                        Err(e) => return Err(SolverError::SolutionParse { line: index + 2, reason: e.to_string() }),
                     }
                 } else {
-                    return Err("Incorrect solution format".to_string());
+                    return Err(SolverError::SolutionParse { line: index + 2, reason: "expected a \"name value\" pair".to_string() });
                 }
             }
         } else {
-            return Err("Incorrect solution format".to_string());
+            return Err(SolverError::SolutionParse { line: 1, reason: "missing header line".to_string() });
         }
         // TODO/FIX: always optimal if no err...
         if let Some(p) = problem {
@@ -95,26 +104,55 @@ impl SolverWithSolutionParsing for GurobiSolver {
 
 impl SolverTrait for GurobiSolver {
     type P = LpProblem;
-    fn run<'a>(&self, problem: &'a Self::P) -> Result<Solution<'a>, String> {
+    fn run<'a>(&self, problem: &'a Self::P) -> Result<Solution<'a>, SolverError> {
         let file_model = &format!("{}.lp", problem.unique_name);
 
-        match problem.write_lp(file_model) {
-            Ok(_) => {
-                let result = match Command::new(&self.command_name)
-                    .arg(format!("ResultFile={}", self.temp_solution_file))
-                    .arg(file_model)
-                    .output()
-                    {
-                        Ok(r) => {
-                            self.process_output(problem, r)
-                        }
-                        Err(_) => Err(format!("Error running the {} solver", self.name)),
-                    };
-                let _ = fs::remove_file(&file_model);
+        problem.write_lp(file_model)?;
+
+        let result = match Command::new(&self.command_name)
+            .arg(format!("ResultFile={}", self.temp_solution_file))
+            .arg(file_model)
+            .output()
+            {
+                Ok(r) => {
+                    self.process_output(problem, r)
+                }
+                Err(_) => Err(SolverError::Spawn(self.name.clone())),
+            };
+        let _ = fs::remove_file(&file_model);
+
+        result
+    }
+}
+
+impl AsyncSolverTrait for GurobiSolver {
+    type P = LpProblem;
+    fn spawn(&self, problem: &Self::P) -> Result<SolveHandle, SolverError> {
+        let file_model = format!("{}.lp", problem.unique_name);
+        // A fresh path per call, not `self.temp_solution_file` - concurrent
+        // `spawn()` calls on the same solver must not point two child
+        // processes at the same `.sol` file.
+        let temp_solution_file = format!("{}.sol", Uuid::new_v4().to_string());
+
+        problem.write_lp(&file_model)?;
 
-                result
+        match Command::new(&self.command_name)
+            .arg(format!("ResultFile={}", temp_solution_file))
+            .arg(&file_model)
+            .stdout(Stdio::piped())
+            .spawn()
+        {
+            Ok(child) => Ok(SolveHandle::new(child, file_model, temp_solution_file)),
+            Err(_) => {
+                let _ = fs::remove_file(&file_model);
+                Err(SolverError::Spawn(self.name.clone()))
             }
-            Err(e) => Err(e.to_string()),
         }
     }
 }
+
+impl SolverStdoutStatus for GurobiSolver {
+    fn detect_status(&self, stdout: &[u8]) -> Status {
+        status_from_stdout(&String::from_utf8_lossy(stdout))
+    }
+}
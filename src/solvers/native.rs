@@ -0,0 +1,256 @@
+extern crate minilp;
+use self::minilp::{ComparisonOp, OptimizationDirection, Problem};
+
+use std::collections::HashMap;
+
+use dsl::LpExpression::*;
+use dsl::{LpBinary, LpConstraint, LpContinuous, LpExpression, LpInteger, LpObjective, LpProblem};
+use error::SolverError;
+use solvers::{Solution, SolverTrait, Status};
+
+/// A pure-Rust LP solver that needs no external binary: the problem is
+/// flattened into a coefficient model and solved in-process with an
+/// embedded simplex solver.
+///
+/// `NativeSolver` only solves the continuous relaxation of a model -
+/// integer and binary variables are rejected until branch-and-bound is
+/// implemented on top of this.
+pub struct NativeSolver {
+    name: String,
+}
+
+impl NativeSolver {
+    pub fn new() -> NativeSolver {
+        NativeSolver {
+            name: "NativeSolver".to_string(),
+        }
+    }
+}
+
+/// Flattens an `LpExpression` tree into a coefficient map plus a constant
+/// term, e.g. `2 x + 3 y - 4` becomes `({"x": 2.0, "y": 3.0}, -4.0)`.
+///
+/// Returns an `Err` if the expression contains a genuinely nonlinear term
+/// (the product of two variable subtrees), since the native solver only
+/// understands linear models.
+fn flatten_expr(expr: &LpExpression) -> Result<(HashMap<String, f64>, f64), SolverError> {
+    match expr {
+        &LitVal(n) => Ok((HashMap::new(), n)),
+        &AddExpr(ref e1, ref e2) => {
+            let (mut coeffs, const1) = flatten_expr(e1)?;
+            let (coeffs2, const2) = flatten_expr(e2)?;
+            for (name, coeff) in coeffs2 {
+                *coeffs.entry(name).or_insert(0.0) += coeff;
+            }
+            Ok((coeffs, const1 + const2))
+        }
+        &SubExpr(ref e1, ref e2) => {
+            let (mut coeffs, const1) = flatten_expr(e1)?;
+            let (coeffs2, const2) = flatten_expr(e2)?;
+            for (name, coeff) in coeffs2 {
+                *coeffs.entry(name).or_insert(0.0) -= coeff;
+            }
+            Ok((coeffs, const1 - const2))
+        }
+        &MulExpr(ref e1, ref e2) => {
+            let (coeffs1, const1) = flatten_expr(e1)?;
+            let (coeffs2, const2) = flatten_expr(e2)?;
+            if !coeffs1.is_empty() && !coeffs2.is_empty() {
+                return Err(SolverError::InvalidModel(
+                    "NativeSolver only supports linear models, but found the product of two variables"
+                        .to_string(),
+                ));
+            }
+            let mut coeffs = HashMap::new();
+            for (name, coeff) in &coeffs1 {
+                coeffs.insert(name.clone(), coeff * const2);
+            }
+            for (name, coeff) in &coeffs2 {
+                coeffs.insert(name.clone(), coeff * const1);
+            }
+            Ok((coeffs, const1 * const2))
+        }
+        &ConsBin(LpBinary { ref name }) => Ok(([(name.clone(), 1.0)].iter().cloned().collect(), 0.0)),
+        &ConsInt(LpInteger { ref name, .. }) => {
+            Ok(([(name.clone(), 1.0)].iter().cloned().collect(), 0.0))
+        }
+        &ConsCont(LpContinuous { ref name, .. }) => {
+            Ok(([(name.clone(), 1.0)].iter().cloned().collect(), 0.0))
+        }
+        // The identity element used e.g. when summing an empty collection
+        // of terms - contributes nothing to either the coefficients or the
+        // constant, same as `LitVal(0.0)`.
+        &EmptyExpr => Ok((HashMap::new(), 0.0)),
+        _ => Err(SolverError::InvalidModel(
+            "NativeSolver cannot flatten this expression".to_string(),
+        )),
+    }
+}
+
+/// Flattens an `LpConstraint(lhs, op, rhs)` into `coeff * var ... op rhs`,
+/// moving every variable to the left-hand side and every constant to the
+/// right-hand side.
+fn flatten_constraint(
+    constraint: &LpConstraint,
+) -> Result<(HashMap<String, f64>, ComparisonOp, f64), SolverError> {
+    use dsl::Constraint::*;
+
+    let (lhs_coeffs, lhs_const) = flatten_expr(&constraint.0)?;
+    let (rhs_coeffs, rhs_const) = flatten_expr(&constraint.2)?;
+
+    let mut coeffs = lhs_coeffs;
+    for (name, coeff) in rhs_coeffs {
+        *coeffs.entry(name).or_insert(0.0) -= coeff;
+    }
+
+    let op = match constraint.1 {
+        LessOrEqual => ComparisonOp::Le,
+        GreaterOrEqual => ComparisonOp::Ge,
+        Equal => ComparisonOp::Eq,
+    };
+
+    Ok((coeffs, op, rhs_const - lhs_const))
+}
+
+/// Maps a `minilp` solve failure onto a `Status`. Infeasible and unbounded are kept
+/// distinct: unbounded means the model *is* feasible but has no finite
+/// optimum, which is a different outcome from having no feasible point.
+fn status_for_solve_error(e: &minilp::Error) -> Status {
+    match e {
+        &minilp::Error::Infeasible => Status::Infeasible,
+        &minilp::Error::Unbounded => Status::Unbounded,
+    }
+}
+
+impl SolverTrait for NativeSolver {
+    type P = LpProblem;
+
+    fn run<'a>(&self, problem: &'a Self::P) -> Result<Solution<'a>, SolverError> {
+        let direction = match problem.objective_type {
+            LpObjective::Maximize => OptimizationDirection::Maximize,
+            LpObjective::Minimize => OptimizationDirection::Minimize,
+        };
+
+        let obj_coeffs = match problem.obj_expr {
+            Some(ref expr) => flatten_expr(expr)?.0,
+            None => HashMap::new(),
+        };
+
+        let mut lp_problem = Problem::new(direction);
+        let mut vars = HashMap::new();
+
+        for (name, v) in problem.variables() {
+            match v {
+                &ConsCont(LpContinuous {
+                    lower_bound,
+                    upper_bound,
+                    ..
+                }) => {
+                    let bounds = (
+                        lower_bound.unwrap_or(std::f64::NEG_INFINITY),
+                        upper_bound.unwrap_or(std::f64::INFINITY),
+                    );
+                    let coeff = obj_coeffs.get(name).cloned().unwrap_or(0.0);
+                    vars.insert(name.clone(), lp_problem.add_var(coeff, bounds));
+                }
+                &ConsInt(_) | &ConsBin(_) => {
+                    return Err(SolverError::InvalidModel(format!(
+                        "{} only solves the continuous relaxation - integer and binary variables are not supported until branch-and-bound is implemented",
+                        self.name
+                    )));
+                }
+                _ => {}
+            }
+        }
+
+        for constraint in &problem.constraints {
+            let (coeffs, op, rhs) = flatten_constraint(constraint)?;
+            let terms: Vec<_> = coeffs
+                .iter()
+                .filter_map(|(name, &coeff)| vars.get(name).map(|&var| (var, coeff)))
+                .collect();
+            lp_problem.add_constraint(&terms, op, rhs);
+        }
+
+        match lp_problem.solve() {
+            Ok(solution) => {
+                let vars_value = vars
+                    .iter()
+                    .map(|(name, &var)| (name.clone(), solution[var] as f32))
+                    .collect();
+                Ok(Solution::with_problem(Status::Optimal, vars_value, problem))
+            }
+            // Infeasibility/unboundedness is a normal solve outcome, not a
+            // structural failure - report it the way GurobiSolver does
+            // rather than erroring out, so callers written against
+            // `SolverTrait` see the same `Ok(Solution)` shape either way.
+            Err(ref e) => Ok(Solution::with_problem(
+                status_for_solve_error(e),
+                HashMap::new(),
+                problem,
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var(name: &str) -> LpExpression {
+        ConsCont(LpContinuous {
+            name: name.to_string(),
+            lower_bound: None,
+            upper_bound: None,
+        })
+    }
+
+    #[test]
+    fn flattens_linear_expression() {
+        // 2x + 3y - 4
+        let expr = SubExpr(
+            Box::new(AddExpr(
+                Box::new(MulExpr(Box::new(LitVal(2.0)), Box::new(var("x")))),
+                Box::new(MulExpr(Box::new(LitVal(3.0)), Box::new(var("y")))),
+            )),
+            Box::new(LitVal(4.0)),
+        );
+
+        let (coeffs, constant) = flatten_expr(&expr).unwrap();
+
+        assert_eq!(coeffs.get("x"), Some(&2.0));
+        assert_eq!(coeffs.get("y"), Some(&3.0));
+        assert_eq!(constant, -4.0);
+    }
+
+    #[test]
+    fn rejects_product_of_two_variables_as_nonlinear() {
+        let expr = MulExpr(Box::new(var("x")), Box::new(var("y")));
+
+        match flatten_expr(&expr) {
+            Err(SolverError::InvalidModel(_)) => {}
+            Ok(_) => panic!("expected an error for a nonlinear product"),
+            Err(e) => panic!("expected InvalidModel, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn empty_expr_contributes_nothing() {
+        let (coeffs, constant) = flatten_expr(&EmptyExpr).unwrap();
+
+        assert!(coeffs.is_empty());
+        assert_eq!(constant, 0.0);
+    }
+
+    #[test]
+    fn infeasible_and_unbounded_map_to_distinct_statuses() {
+        match status_for_solve_error(&minilp::Error::Infeasible) {
+            Status::Infeasible => {}
+            _ => panic!("expected Status::Infeasible"),
+        }
+        match status_for_solve_error(&minilp::Error::Unbounded) {
+            Status::Unbounded => {}
+            _ => panic!("expected Status::Unbounded"),
+        }
+    }
+}